@@ -1,18 +1,30 @@
 #![forbid(unsafe_code)]
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, Read, Write};
+#[cfg(unix)]
 use std::os::unix;
+#[cfg(windows)]
+use std::os::windows;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use clap::ArgMatches;
 use failure::ResultExt;
+use sha2::{Digest, Sha256};
 
 /// Handle all subcommands and calls appropriate function
 #[inline]
 pub fn run(matches: &ArgMatches) -> Result<(), failure::Error> {
-    let config_paths = get_config_paths()?;
+    let mut config_paths = get_config_paths(matches.value_of("config-dir"))?;
 
     create_config_dir(&config_paths.config_dir).with_context(|_| "failed to create config dir")?;
 
+    // Resolve symlinked home/data dirs now that the directory exists, so the
+    // path compared against `.npmrc`'s link target in `status` is canonical.
+    if let Ok(canonical) = fs::canonicalize(&config_paths.config_dir) {
+        config_paths.config_dir = canonical;
+    }
+
     match matches.subcommand() {
         ("create", Some(create_matches)) => {
             let profile = create_matches.value_of("profile").unwrap();
@@ -24,27 +36,53 @@ pub fn run(matches: &ArgMatches) -> Result<(), failure::Error> {
             list_all_profiles(&config_paths.config_dir).with_context(|_| "Failed to list all profiles")?;
         }
         ("open", Some(open_matcher)) => {
-            let profile = open_matcher.value_of("profile").unwrap();
             let editor = open_matcher.value_of("editor").unwrap();
+            let profile = match open_matcher.value_of("profile") {
+                Some(profile) => profile.to_string(),
+                None => pick_profile(&config_paths.config_dir)?,
+            };
 
-            open_profile(profile, &config_paths.config_dir, editor)
+            open_profile(&profile, &config_paths.config_dir, editor)
                 .with_context(|_| format!("Failed to open profile \"{}\"", profile))?;
         }
         ("activate", Some(activate_matcher)) => {
-            let profile = activate_matcher.value_of("profile").unwrap();
+            let profile = match activate_matcher.value_of("profile") {
+                Some(profile) => profile.to_string(),
+                None => pick_profile(&config_paths.config_dir)?,
+            };
+
+            let copy = activate_matcher.is_present("copy");
 
-            activate_profile(profile, &config_paths.config_dir, &config_paths.home_dir)
+            activate_profile(&profile, &config_paths.config_dir, &config_paths.home_dir, copy)
                 .with_context(|_| format!("Failed to activate profile \"{}\"", profile))?;
         }
         ("status", Some(_)) => {
             show_active_profile(&config_paths.config_dir, &config_paths.home_dir);
         }
         ("remove", Some(remove_matches)) => {
-            let profile = remove_matches.value_of("profile").unwrap();
+            let profile = match remove_matches.value_of("profile") {
+                Some(profile) => profile.to_string(),
+                None => pick_profile(&config_paths.config_dir)?,
+            };
 
-            remove_profile(profile, &config_paths.config_dir)
+            remove_profile(&profile, &config_paths.config_dir)
                 .with_context(|_| format!("Failed to remove profile \"{}\"", profile))?;
         }
+        ("export", Some(export_matches)) => {
+            let profile = export_matches.value_of("profile").unwrap();
+            let output = export_matches.value_of("output");
+            let redact = export_matches.is_present("redact");
+
+            export_profile(profile, &config_paths.config_dir, output, redact)
+                .with_context(|_| format!("Failed to export profile \"{}\"", profile))?;
+        }
+        ("import", Some(import_matches)) => {
+            let name = import_matches.value_of("name").unwrap();
+            let from = import_matches.value_of("from").unwrap();
+
+            import_profile(name, &config_paths.config_dir, from)
+                .with_context(|_| format!("Failed to import profile \"{}\"", name))?;
+        }
         ("", None) => return Err(failure::err_msg("no subcommand was used")),
         _ => unreachable!(),
     };
@@ -61,14 +99,27 @@ struct ConfigPaths {
 }
 
 /// Gets all config paths including the home directory and the config directory paths
-fn get_config_paths() -> Result<ConfigPaths, failure::Error> {
+///
+/// The config directory is chosen, in order of precedence, from the
+/// `--config-dir` flag, the `RNPMRC_DIR` environment variable, and
+/// `dirs::data_dir()/rnpmrc`, falling back to `~/.rnpmrc` only when the
+/// platform data directory is unavailable.
+fn get_config_paths(config_dir_flag: Option<&str>) -> Result<ConfigPaths, failure::Error> {
     let home_dir = match dirs::home_dir() {
         Some(path) => path,
         None => return Err(failure::err_msg("did not find home directory")),
     };
 
-    let mut config_dir = PathBuf::from(&home_dir);
-    config_dir.push(".rnpmrc");
+    let config_dir = if let Some(flag) = config_dir_flag {
+        PathBuf::from(flag)
+    } else if let Some(env_dir) = std::env::var_os("RNPMRC_DIR") {
+        PathBuf::from(env_dir)
+    } else if let Some(mut data_dir) = dirs::data_dir() {
+        data_dir.push("rnpmrc");
+        data_dir
+    } else {
+        build_file_path(&home_dir, ".rnpmrc")
+    };
 
     Ok(ConfigPaths { home_dir, config_dir })
 }
@@ -108,30 +159,70 @@ fn create_profile(profile: &str, config_dir: &Path) -> Result<(), failure::Error
 
 /// Lists all profiles in `.rnpmrc` directory
 fn list_all_profiles(config_dir: &Path) -> Result<(), failure::Error> {
-    let paths = fs::read_dir(config_dir)?;
     let mut file_names = String::new();
 
+    for name in parse_profile_names(config_dir)? {
+        file_names.push_str(&format!(".npmrc.{}\n", name));
+    }
+
+    println!("{}", file_names);
+
+    Ok(())
+}
+
+/// Scans the config directory for `.npmrc.<name>` files and returns the
+/// profile names, sorted
+fn parse_profile_names(config_dir: &Path) -> Result<Vec<String>, failure::Error> {
+    let paths = fs::read_dir(config_dir)?;
+    let mut names = Vec::new();
+
     for entry in paths {
         let entry = entry?;
         let path = entry.path();
 
         if path.is_file() {
-            let file_name = match path.file_name() {
-                Some(parsed_file_name) => parsed_file_name,
-                None => return Err(failure::err_msg("Failed reading files".to_string())),
-            };
-
-            if let Some(file_name_str) = file_name.to_str() {
-                if file_name_str.contains(".npmrc.") {
-                    file_names.push_str(&format!("{}\n", file_name_str));
-                }
+            if let Some(name) = profile_name(&path) {
+                names.push(name);
             }
         }
     }
 
-    println!("{}", file_names);
+    names.sort();
 
-    Ok(())
+    Ok(names)
+}
+
+/// Prints a numbered menu of profiles and reads a selection from stdin,
+/// accepting either a 1-based index or an exact profile name
+fn pick_profile(config_dir: &Path) -> Result<String, failure::Error> {
+    let names = parse_profile_names(config_dir)?;
+
+    if names.is_empty() {
+        return Err(failure::err_msg("no profiles found"));
+    }
+
+    for (index, name) in names.iter().enumerate() {
+        println!("{}) {}", index + 1, name);
+    }
+
+    print!("Select a profile: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let choice = input.trim();
+
+    if let Ok(index) = choice.parse::<usize>() {
+        if index >= 1 && index <= names.len() {
+            return Ok(names[index - 1].clone());
+        }
+    }
+
+    if let Some(name) = names.iter().find(|name| name.as_str() == choice) {
+        return Ok(name.clone());
+    }
+
+    Err(failure::err_msg(format!("invalid selection \"{}\"", choice)))
 }
 
 /// Opens a profile in editor, default is vi
@@ -168,13 +259,87 @@ fn remove_profile(profile: &str, config_dir: &Path) -> Result<(), failure::Error
     )))
 }
 
-/// Creates a symbolic link from the profile to `.npmrc`
+/// Exports a profile's contents to a file or stdout
+/// With `redact`, any line holding a registry credential is dropped first
+fn export_profile(
+    profile: &str,
+    config_dir: &Path,
+    output: Option<&str>,
+    redact: bool,
+) -> Result<(), failure::Error> {
+    let file_path = build_file_path(config_dir, &format!(".npmrc.{}", profile));
+
+    if !file_path.is_file() {
+        return Err(failure::err_msg(format!(
+            "file {:?} doesn't exists",
+            file_path
+        )));
+    }
+
+    let mut contents = fs::read_to_string(&file_path)?;
+
+    if redact {
+        contents = contents
+            .lines()
+            .filter(|line| !is_secret_line(line))
+            .map(|line| format!("{}\n", line))
+            .collect();
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(path, contents)?;
+        }
+        None => {
+            print!("{}", contents);
+            io::stdout().flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a new profile from a file or stdin (`-`)
+/// Throws error if profile with the same name already exists
+fn import_profile(name: &str, config_dir: &Path, from: &str) -> Result<(), failure::Error> {
+    let file_path = build_file_path(config_dir, &format!(".npmrc.{}", name));
+
+    if file_path.is_file() {
+        return Err(failure::err_msg(format!(
+            "file {:?} already exists",
+            file_path
+        )));
+    }
+
+    let contents = if from == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        fs::read_to_string(from)?
+    };
+
+    print!("Creating file {:?}... ", file_path);
+    fs::write(&file_path, contents)?;
+    println!("Succeed");
+
+    Ok(())
+}
+
+/// Whether a line carries a registry credential that must not be shared
+fn is_secret_line(line: &str) -> bool {
+    line.contains("_authToken") || line.contains("_password") || line.contains(":_auth=")
+}
+
+/// Activates a profile by linking it to `.npmrc`, or copying it when `copy`
+/// is set (for platforms or environments where symlinks are unavailable)
 /// Remove `.npmrc` file if it exists
 /// Throws error if profile not found
 fn activate_profile(
     profile: &str,
     config_dir: &Path,
     home_dir: &Path,
+    copy: bool,
 ) -> Result<(), failure::Error> {
     let file_path = build_file_path(config_dir, &format!(".npmrc.{}", profile));
     let npmrc_path = build_file_path(home_dir, ".npmrc");
@@ -192,28 +357,85 @@ fn activate_profile(
         println!("Succeed");
     }
 
-    print!("Creating symlink for {:?}... ", file_path);
-    unix::fs::symlink(&file_path, &npmrc_path)?;
-    println!("Succeed");
+    let mode = if copy {
+        print!("Copying {:?}... ", file_path);
+        fs::copy(&file_path, &npmrc_path)?;
+        println!("Succeed");
+        ActivationMode::Copy
+    } else {
+        print!("Creating symlink for {:?}... ", file_path);
+        symlink_file(&file_path, &npmrc_path)?;
+        println!("Succeed");
+        ActivationMode::Link
+    };
+
+    let entry = ManifestEntry {
+        hash: hash_file(&file_path)?,
+        mode,
+    };
+    let mut manifest = read_manifest(config_dir)?;
+    manifest.insert(profile.to_string(), entry);
+    write_manifest(config_dir, &manifest)?;
 
     Ok(())
 }
 
+/// Creates a symbolic link, using the platform's native symlink primitive
+#[cfg(unix)]
+fn symlink_file(src: &Path, dst: &Path) -> Result<(), failure::Error> {
+    unix::fs::symlink(src, dst)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn symlink_file(src: &Path, dst: &Path) -> Result<(), failure::Error> {
+    windows::fs::symlink_file(src, dst)?;
+    Ok(())
+}
+
 /// Shows current active profile
 /// Active profile is what the `.npmrc` file is being linked to
+///
+/// When a profile is activated its contents are hashed into the manifest, so
+/// we can tell a pristine link from one whose target has been edited since,
+/// and recognise a plain `.npmrc` that happens to match a known profile. A
+/// plain `.npmrc` matching a profile that was activated with `copy` is
+/// reported as that profile being active.
 fn show_active_profile(config_dir: &Path, home_dir: &Path) {
     let npmrc_path = build_file_path(home_dir, ".npmrc");
-
-    if let Ok(info) = fs::read_link(&npmrc_path) {
-        if info.is_file() && info.starts_with(&config_dir) {
-            if let Some(file_name) = info.file_name() {
-                println!("{:?} is active", file_name);
-            } else {
-                println!("No active profile");
+    let manifest = read_manifest(config_dir).unwrap_or_default();
+
+    if let Ok(target) = fs::read_link(&npmrc_path) {
+        if target.is_file() && target.starts_with(&config_dir) {
+            if let Some(profile) = profile_name(&target) {
+                match manifest.get(&profile) {
+                    None => println!("{} is active (unknown, not in manifest)", profile),
+                    Some(entry) => match hash_file(&target) {
+                        Ok(ref current) if *current == entry.hash => println!("{} is active", profile),
+                        Ok(_) => println!("{} is active (modified since activation)", profile),
+                        Err(_) => println!("{} is active", profile),
+                    },
+                }
+                return;
             }
-        } else {
-            println!("No active profile");
         }
+
+        println!("No active profile");
+        return;
+    }
+
+    if npmrc_path.is_file() {
+        match hash_file(&npmrc_path) {
+            Ok(current) => match manifest.iter().find(|(_, entry)| entry.hash == current) {
+                Some((profile, entry)) => match entry.mode {
+                    ActivationMode::Copy => println!("{} is active (copied)", profile),
+                    ActivationMode::Link => println!("matches profile {} but not linked", profile),
+                },
+                None => println!("untracked local .npmrc"),
+            },
+            Err(_) => println!("untracked local .npmrc"),
+        }
+        return;
     }
 
     println!("No active profile");
@@ -229,6 +451,108 @@ fn build_file_path(dir_path: &Path, file_name: &str) -> PathBuf {
     file_path
 }
 
+/// Returns the profile name for a `.npmrc.<name>` file, if it is one
+fn profile_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(".npmrc.").map(|rest| rest.to_string()))
+}
+
+/// Computes the lowercase hex SHA-256 digest of a file's contents
+fn hash_file(path: &Path) -> Result<String, failure::Error> {
+    let contents = fs::read(path)?;
+    let mut hasher = Sha256::new();
+    hasher.input(&contents);
+
+    Ok(hasher.result().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// How a profile was wired into `.npmrc` at activation time
+enum ActivationMode {
+    /// `.npmrc` is a symlink pointing at the profile
+    Link,
+    /// `.npmrc` is a copy of the profile's contents
+    Copy,
+}
+
+impl ActivationMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ActivationMode::Link => "link",
+            ActivationMode::Copy => "copy",
+        }
+    }
+
+    fn from_str(value: &str) -> ActivationMode {
+        match value {
+            "copy" => ActivationMode::Copy,
+            _ => ActivationMode::Link,
+        }
+    }
+}
+
+/// What the manifest records for each activated profile
+struct ManifestEntry {
+    /// SHA-256 of the profile's contents at activation time
+    hash: String,
+    /// Whether it was linked or copied
+    mode: ActivationMode,
+}
+
+/// Path to the activation manifest, `manifest.toml` in the config directory
+fn manifest_path(config_dir: &Path) -> PathBuf {
+    build_file_path(config_dir, "manifest.toml")
+}
+
+/// Reads the manifest mapping profile names to the digest and mode recorded
+/// at activation time. A missing manifest is an empty map, not an error.
+fn read_manifest(config_dir: &Path) -> Result<HashMap<String, ManifestEntry>, failure::Error> {
+    let path = manifest_path(config_dir);
+
+    if !path.is_file() {
+        return Ok(HashMap::new());
+    }
+
+    let mut manifest = HashMap::new();
+    let contents = fs::read_to_string(&path)?;
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((name, value)) = line.split_once('=') {
+            let name = name.trim();
+            let value = value.trim().trim_matches('"');
+            let mut fields = value.split_whitespace();
+
+            if let (false, Some(hash)) = (name.is_empty(), fields.next()) {
+                let mode = ActivationMode::from_str(fields.next().unwrap_or("link"));
+                manifest.insert(name.to_string(), ManifestEntry { hash: hash.to_string(), mode });
+            }
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Writes the manifest back, one `name = "<digest> <mode>"` entry per profile
+fn write_manifest(config_dir: &Path, manifest: &HashMap<String, ManifestEntry>) -> Result<(), failure::Error> {
+    let mut entries: Vec<_> = manifest.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut contents = String::new();
+    for (name, entry) in entries {
+        contents.push_str(&format!("{} = \"{} {}\"\n", name, entry.hash, entry.mode.as_str()));
+    }
+
+    fs::write(manifest_path(config_dir), contents)?;
+
+    Ok(())
+}
+
 /// Checks if the path exits or is a symbolic link
 fn exists_or_symlinked(path: &Path) -> bool {
     if path.is_file() || path.is_dir() {
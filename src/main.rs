@@ -13,6 +13,14 @@ fn main() -> Result<(), ExitFailure> {
         .about("A simple tool to manage multiple .npmrc files")
         .version(crate_version!())
         .author(crate_authors!())
+        .arg(
+            Arg::with_name("config-dir")
+                .long("config-dir")
+                .help("Directory that holds all profiles")
+                .value_name("DIR")
+                .global(true)
+                .takes_value(true),
+        )
         .subcommand(
             SubCommand::with_name("create")
                 .about("Creates new profile")
@@ -29,7 +37,7 @@ fn main() -> Result<(), ExitFailure> {
                 .arg(
                     Arg::with_name("profile")
                         .help("Profile name")
-                        .required(true),
+                        .required(false),
                 )
                 .arg(
                     Arg::with_name("editor")
@@ -47,7 +55,7 @@ fn main() -> Result<(), ExitFailure> {
                 .arg(
                     Arg::with_name("profile")
                         .help("Profile name")
-                        .required(true),
+                        .required(false),
                 ),
         )
         .subcommand(
@@ -56,10 +64,54 @@ fn main() -> Result<(), ExitFailure> {
                 .arg(
                     Arg::with_name("profile")
                         .help("Profile name")
-                        .required(true),
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("copy")
+                        .long("copy")
+                        .help("Copy the profile into .npmrc instead of linking"),
                 ),
         )
         .subcommand(SubCommand::with_name("status").about("Shows current activate profile"))
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Exports a profile to stdout or a file")
+                .arg(
+                    Arg::with_name("profile")
+                        .help("Profile name")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("Write to a file instead of stdout")
+                        .value_name("FILE")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("redact")
+                        .long("redact")
+                        .help("Strip lines containing registry credentials"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Creates a profile from a file or stdin")
+                .arg(
+                    Arg::with_name("name")
+                        .help("Profile name")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .help("Source file path, or \"-\" for stdin")
+                        .value_name("PATH")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("backup")
                 .about("Creates a profile from .npmrc file")